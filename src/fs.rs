@@ -0,0 +1,275 @@
+//! Filesystem abstraction so command logic can be exercised without
+//! touching the real disk. [`RealFs`] is what `main` wires up in
+//! production; [`FakeFs`] (only built for tests or under `test-support`) backs unit
+//! tests with an in-memory tree.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The filesystem operations the `dr` commands need. Implemented by
+/// [`RealFs`] over `std::fs` and, for tests, by [`FakeFs`].
+pub trait Fs: Send + Sync {
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<u64>;
+    fn copy_dir_all(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    fn exists(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()>;
+    fn append(&self, path: &Path, contents: &str) -> io::Result<()>;
+}
+
+/// [`Fs`] implementation backed by the real operating system filesystem.
+pub struct RealFs;
+
+impl Fs for RealFs {
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        std::fs::copy(from, to)
+    }
+
+    fn copy_dir_all(&self, from: &Path, to: &Path) -> io::Result<()> {
+        crate::copy_dir_all(self, from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|e| e.map(|e| e.path()))
+            .collect()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    fn append(&self, path: &Path, contents: &str) -> io::Result<()> {
+        use std::io::Write;
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(contents.as_bytes())
+    }
+}
+
+// `test-support` also builds `FakeFs` outside of tests; within this
+// bin-only crate nothing outside `#[cfg(test)]` ever names it,
+// so building with just the feature (and not `cfg(test)`) would otherwise
+// be entirely dead code.
+#[cfg(any(test, feature = "test-support"))]
+#[cfg_attr(not(test), allow(dead_code, unused_imports))]
+pub use fake::FakeFs;
+
+#[cfg(any(test, feature = "test-support"))]
+#[cfg_attr(not(test), allow(dead_code))]
+mod fake {
+    use super::Fs;
+    use std::{
+        collections::BTreeMap,
+        io,
+        path::{Path, PathBuf},
+        sync::Mutex,
+    };
+
+    #[derive(Debug, Clone)]
+    enum Node {
+        File(Vec<u8>),
+        Dir,
+    }
+
+    /// In-memory [`Fs`] implementation for tests. Paths are stored as
+    /// given (no real canonicalization), which is good enough for
+    /// exercising the command logic in isolation.
+    #[derive(Default)]
+    pub struct FakeFs {
+        nodes: Mutex<BTreeMap<PathBuf, Node>>,
+    }
+
+    impl FakeFs {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn insert_file(&self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+            let path = path.into();
+            if let Some(parent) = path.parent() {
+                self.ensure_dir(parent);
+            }
+            self.nodes
+                .lock()
+                .unwrap()
+                .insert(path, Node::File(contents.into()));
+        }
+
+        pub fn insert_dir(&self, path: impl Into<PathBuf>) {
+            self.ensure_dir(&path.into());
+        }
+
+        fn ensure_dir(&self, path: &Path) {
+            let mut nodes = self.nodes.lock().unwrap();
+            let mut cur = PathBuf::new();
+            for component in path.components() {
+                cur.push(component);
+                nodes.entry(cur.clone()).or_insert(Node::Dir);
+            }
+        }
+
+        pub fn file_contents(&self, path: &Path) -> Option<Vec<u8>> {
+            match self.nodes.lock().unwrap().get(path) {
+                Some(Node::File(bytes)) => Some(bytes.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    impl Fs for FakeFs {
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            let mut nodes = self.nodes.lock().unwrap();
+            match nodes.get(from) {
+                Some(Node::Dir) => {
+                    // Directories never rename atomically in the fake fs, so
+                    // drop_entries always falls back to its recursive copy
+                    // path for them, exactly like a real cross-device move.
+                    return Err(io::Error::from(io::ErrorKind::CrossesDevices));
+                }
+                Some(Node::File(_)) => {}
+                None => return Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+            }
+            let node = nodes.remove(from).unwrap();
+            nodes.insert(to.to_path_buf(), node);
+            Ok(())
+        }
+
+        fn copy_file(&self, from: &Path, to: &Path) -> io::Result<u64> {
+            let mut nodes = self.nodes.lock().unwrap();
+            let bytes = match nodes.get(from) {
+                Some(Node::File(bytes)) => bytes.clone(),
+                _ => return Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+            };
+            let len = bytes.len() as u64;
+            nodes.insert(to.to_path_buf(), Node::File(bytes));
+            Ok(len)
+        }
+
+        fn copy_dir_all(&self, from: &Path, to: &Path) -> io::Result<()> {
+            crate::copy_dir_all(self, from, to)
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.nodes
+                .lock()
+                .unwrap()
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+            let mut nodes = self.nodes.lock().unwrap();
+            let to_remove: Vec<PathBuf> = nodes
+                .keys()
+                .filter(|p| *p == path || p.starts_with(path))
+                .cloned()
+                .collect();
+            for p in to_remove {
+                nodes.remove(&p);
+            }
+            Ok(())
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+            let nodes = self.nodes.lock().unwrap();
+            Ok(nodes
+                .keys()
+                .filter(|p| p.parent() == Some(path))
+                .cloned()
+                .collect())
+        }
+
+        fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+            self.ensure_dir(path);
+            Ok(())
+        }
+
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            Ok(path.to_path_buf())
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.nodes.lock().unwrap().contains_key(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            matches!(self.nodes.lock().unwrap().get(path), Some(Node::Dir))
+        }
+
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            match self.nodes.lock().unwrap().get(path) {
+                Some(Node::File(bytes)) => String::from_utf8(bytes.clone())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+                Some(Node::Dir) => Err(io::Error::new(io::ErrorKind::InvalidInput, "is a dir")),
+                None => Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+            }
+        }
+
+        fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+            self.nodes
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), Node::File(contents.as_bytes().to_vec()));
+            Ok(())
+        }
+
+        fn append(&self, path: &Path, contents: &str) -> io::Result<()> {
+            let mut nodes = self.nodes.lock().unwrap();
+            match nodes.get_mut(path) {
+                Some(Node::File(bytes)) => {
+                    bytes.extend_from_slice(contents.as_bytes());
+                }
+                _ => {
+                    nodes.insert(path.to_path_buf(), Node::File(contents.as_bytes().to_vec()));
+                }
+            }
+            Ok(())
+        }
+    }
+}
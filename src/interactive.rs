@@ -0,0 +1,60 @@
+//! Editor-based interactive selection for `dr -r`/`dr -d` with no
+//! filepaths given. Borrowed from mmv's editor workflow: the current
+//! manifest is written to a temp file, `$EDITOR` is spawned on it, and
+//! whatever entries remain in the saved file are acted on; entries whose
+//! line the user deleted are left untouched.
+
+use crate::fs::Fs;
+use crate::{read_manifest, ManifestEntry};
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::Command,
+};
+
+fn format_entry(entry: &ManifestEntry) -> String {
+    format!(
+        "{}\t{}\tdropped_at={}",
+        entry.id,
+        entry.original_abs_path.display(),
+        entry.dropped_at_unix,
+    )
+}
+
+fn line_id(line: &str) -> Option<&str> {
+    line.split('\t').next().filter(|s| !s.is_empty())
+}
+
+/// Writes the current manifest to a temp file, opens `$EDITOR` on it, and
+/// returns the ids of entries the user left in the file. Selecting by id
+/// (rather than original path) matters because two drops of the same path
+/// are distinct manifest lines; keeping only one must act on only that one.
+/// Returns an empty vec (with nothing acted on) if the manifest is empty.
+pub(crate) fn select_entries(fs: &dyn Fs, root: &Path) -> io::Result<Vec<String>> {
+    let entries = read_manifest(fs, root)?;
+    if entries.is_empty() {
+        println!("Nothing dropped yet.");
+        return Ok(Vec::new());
+    }
+
+    let mut tmp = tempfile::NamedTempFile::new()?;
+    for entry in &entries {
+        writeln!(tmp, "{}", format_entry(entry))?;
+    }
+    tmp.flush()?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let status = Command::new(editor).arg(tmp.path()).status()?;
+    if !status.success() {
+        return Err(io::Error::other("editor exited with a non-zero status"));
+    }
+
+    let edited = std::fs::read_to_string(tmp.path())?;
+    let kept_ids: Vec<&str> = edited.lines().filter_map(line_id).collect();
+
+    Ok(entries
+        .into_iter()
+        .filter(|e| kept_ids.contains(&e.id.as_str()))
+        .map(|e| e.id)
+        .collect())
+}
@@ -3,13 +3,25 @@
 // dr foo.txt     drop the file
 // dr -d foo.txt  delete forever
 
+mod fs;
+mod interactive;
+mod matcher;
+
+use crate::fs::{Fs, RealFs};
+use crate::matcher::Matcher;
+use rayon::prelude::*;
 use std::{
-    env, fs, io,
+    collections::hash_map::DefaultHasher,
+    env,
+    hash::{Hash, Hasher},
+    io,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
     time::{SystemTime, UNIX_EPOCH},
 };
 
 const ROOT_DIR: &str = "/tmp/dr";
+const MANIFEST_FILE: &str = "manifest.jsonl";
 const USAGE: &str = r#"
     dr - Drop files from current path until next reboot after which they are
          permanently deleted from the file system.
@@ -18,17 +30,46 @@ const USAGE: &str = r#"
         --delete, -d  Delete a filepath permanently.
         --recover, -r Recover a previously dropped fs entry.
         --list, -l    List all droppped filepaths.
+        --exclude <pattern>    When dropping a directory, skip paths matching
+                               this glob (repeatable).
+        --ignore-file <path>   When dropping a directory, also read exclude
+                               patterns from a gitignore-style file.
+        --dry-run, -n  Show what would happen without touching the fs.
+        --verbose, -v  Print each resolved source/destination pair.
+        --stdin0, -0   Read NUL-separated paths from stdin.
+        --stdin        Read newline-separated paths from stdin.
+        --edit         Pick recover/delete targets by editing the manifest
+                       in $EDITOR. Implied by `dr -r`/`dr -d` with no paths.
     Examples:
         dr foo.txt     drop the file
         dr -r foo.txt  recover the file
         dr -d foo.txt  delete forever
         dr -l          list all dropped files
+        dr --exclude '*.log' --exclude 'target/**' mydir
+        dr -n -v *     preview dropping everything in the current dir
+        find . -name '*.tmp' -print0 | dr -0
 "#;
 
 #[derive(Debug)]
 struct Cli {
     command: Command,
     filepaths: Option<Vec<PathBuf>>,
+    exclude: Vec<String>,
+    ignore_file: Option<PathBuf>,
+    dry_run: bool,
+    verbose: bool,
+    edit: bool,
+}
+
+/// How to read target paths from standard input when none are given on
+/// the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StdinMode {
+    /// `--stdin0`/`-0`: paths separated by NUL bytes, safe for filenames
+    /// containing newlines or spaces.
+    Nul,
+    /// `--stdin`: paths separated by newlines.
+    Lines,
 }
 
 #[derive(Debug, PartialEq)]
@@ -40,6 +81,208 @@ enum Command {
     Help,
 }
 
+/// A single entry in the drop manifest, recording where a dropped file or
+/// directory came from so it can later be listed, recovered or purged
+/// without having to decode any information from its stored name.
+#[derive(Debug, Clone)]
+pub(crate) struct ManifestEntry {
+    pub(crate) id: String,
+    pub(crate) original_abs_path: PathBuf,
+    pub(crate) dropped_at_unix: u64,
+    pub(crate) is_dir: bool,
+    /// Set when this is a directory drop that went through the
+    /// `--exclude`/`--ignore-file` filtered walk, so `original_abs_path`
+    /// still exists on disk (the emptied source directories plus any
+    /// excluded leftovers), whether or not anything was actually skipped.
+    /// Recovering such an entry
+    /// merges the stored subtree back in rather than refusing because the
+    /// original path "already exists".
+    pub(crate) partial: bool,
+}
+
+impl ManifestEntry {
+    fn to_json_line(&self) -> String {
+        format!(
+            "{{\"id\":\"{}\",\"original_abs_path\":\"{}\",\"dropped_at_unix\":{},\"is_dir\":{},\"partial\":{}}}",
+            escape_json(&self.id),
+            escape_json(&self.original_abs_path.to_string_lossy()),
+            self.dropped_at_unix,
+            self.is_dir,
+            self.partial,
+        )
+    }
+
+    fn from_json_line(line: &str) -> Option<ManifestEntry> {
+        let id = extract_json_string(line, "id")?;
+        let original_abs_path = PathBuf::from(extract_json_string(line, "original_abs_path")?);
+        let dropped_at_unix = extract_json_number(line, "dropped_at_unix")?;
+        let is_dir = extract_json_bool(line, "is_dir")?;
+        let partial = extract_json_bool(line, "partial").unwrap_or(false);
+
+        Some(ManifestEntry {
+            id,
+            original_abs_path,
+            dropped_at_unix,
+            is_dir,
+            partial,
+        })
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn extract_json_string(line: &str, key: &str) -> Option<String> {
+    let needle = format!("\"{key}\":\"");
+    let start = line.find(&needle)? + needle.len();
+    let rest = &line[start..];
+    let mut end = None;
+    let mut escaped = false;
+    for (i, c) in rest.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' => {
+                end = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+    Some(unescape_json(&rest[..end]))
+}
+
+fn extract_json_number(line: &str, key: &str) -> Option<u64> {
+    let needle = format!("\"{key}\":");
+    let start = line.find(&needle)? + needle.len();
+    let rest = &line[start..];
+    let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+fn extract_json_bool(line: &str, key: &str) -> Option<bool> {
+    let needle = format!("\"{key}\":");
+    let start = line.find(&needle)? + needle.len();
+    let rest = line[start..].trim_start();
+    if rest.starts_with("true") {
+        Some(true)
+    } else if rest.starts_with("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn manifest_path(root: &Path) -> PathBuf {
+    root.join(MANIFEST_FILE)
+}
+
+pub(crate) fn read_manifest(fs: &dyn Fs, root: &Path) -> io::Result<Vec<ManifestEntry>> {
+    let path = manifest_path(root);
+    let contents = match fs.read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(ManifestEntry::from_json_line)
+        .collect())
+}
+
+fn append_manifest_entry(fs: &dyn Fs, root: &Path, entry: &ManifestEntry) -> io::Result<()> {
+    fs.append(&manifest_path(root), &format!("{}\n", entry.to_json_line()))
+}
+
+fn remove_manifest_entries(fs: &dyn Fs, root: &Path, ids: &[String]) -> io::Result<()> {
+    let entries = read_manifest(fs, root)?;
+    let remaining: Vec<ManifestEntry> = entries
+        .into_iter()
+        .filter(|e| !ids.contains(&e.id))
+        .collect();
+
+    let body = remaining
+        .iter()
+        .map(|e| e.to_json_line())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let body = if body.is_empty() {
+        String::new()
+    } else {
+        format!("{body}\n")
+    };
+
+    fs.write(&manifest_path(root), &body)
+}
+
+/// Process-wide counter folded into [`hash_id`] so two drops of the same
+/// path within the same nanosecond (e.g. a rapid-fire script, or a clock
+/// with coarse resolution) still land on distinct ids.
+static DROP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Produces a filesystem-safe id for a dropped entry, rendered as hex so it
+/// can be used directly as a flat filename regardless of how many path
+/// separators, spaces or unicode characters the original path had. The hash
+/// itself gives no uniqueness guarantee, so it's combined with a nanosecond
+/// timestamp and a per-run counter, and checked against `root` for an actual
+/// collision before being handed back; dropping the same path twice in a row
+/// must never silently overwrite the earlier blob.
+fn hash_id(fs: &dyn Fs, root: &Path, abs_path: &Path) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_nanos();
+
+    loop {
+        let counter = DROP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut hasher = DefaultHasher::new();
+        abs_path.hash(&mut hasher);
+        nanos.hash(&mut hasher);
+        counter.hash(&mut hasher);
+        let id = format!("{:016x}", hasher.finish());
+
+        if !fs.exists(&root.join(&id)) {
+            return id;
+        }
+    }
+}
+
 trait Parse {
     type Item;
 
@@ -49,9 +292,41 @@ trait Parse {
 impl Parse for Cli {
     type Item = Cli;
 
-    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self::Item, String> {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self::Item, String> {
         let mut command = Command::Drop;
         let mut filepaths = Vec::new();
+        let mut exclude = Vec::new();
+        let mut ignore_file = None;
+        let mut dry_run = false;
+        let mut verbose = false;
+        let mut stdin_mode = None;
+        let mut edit = false;
+
+        // --exclude/--ignore-file/--dry-run/--verbose/--stdin*/--edit can
+        // appear anywhere in the argument list, so they're pulled out
+        // first; everything else is handled by the existing command/path
+        // dispatch below.
+        let mut rest = Vec::new();
+        let mut raw = args;
+        while let Some(a) = raw.next() {
+            match a.as_str() {
+                "--exclude" => {
+                    exclude.push(raw.next().ok_or("Missing pattern for --exclude")?);
+                }
+                "--ignore-file" => {
+                    ignore_file = Some(PathBuf::from(
+                        raw.next().ok_or("Missing path for --ignore-file")?,
+                    ));
+                }
+                "--dry-run" | "-n" => dry_run = true,
+                "--verbose" | "-v" => verbose = true,
+                "--stdin0" | "-0" => stdin_mode = Some(StdinMode::Nul),
+                "--stdin" => stdin_mode = Some(StdinMode::Lines),
+                "--edit" => edit = true,
+                _ => rest.push(a),
+            }
+        }
+        let mut args = rest.into_iter();
 
         if let Some(nxt) = args.next() {
             match nxt.as_str() {
@@ -101,9 +376,16 @@ impl Parse for Cli {
             }
         }
 
-        if matches!(command, Command::Drop | Command::Delete | Command::Recover)
-            && filepaths.is_empty()
-        {
+        if filepaths.is_empty() {
+            if let Some(mode) = stdin_mode {
+                filepaths.extend(read_stdin_paths(mode)?);
+            }
+        }
+
+        // Recover/Delete with no filepaths fall back to interactive
+        // editor-based selection instead of erroring; Drop always needs
+        // explicit targets.
+        if command == Command::Drop && filepaths.is_empty() {
             return Err("Missing filepaths".to_string());
         }
 
@@ -114,10 +396,48 @@ impl Parse for Cli {
             } else {
                 Some(filepaths)
             },
+            exclude,
+            ignore_file,
+            dry_run,
+            verbose,
+            edit,
         })
     }
 }
 
+fn normalize_path(raw: &str) -> PathBuf {
+    let path = Path::new(raw);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir().unwrap().join(path)
+    }
+}
+
+/// Reads target paths from stdin for `--stdin0`/`--stdin`, so pipelines
+/// like `find . -name '*.tmp' -print0 | dr -0` work without hitting
+/// ARG_MAX. Paths go through the same absolute-path normalization as
+/// argv entries.
+fn read_stdin_paths(mode: StdinMode) -> Result<Vec<PathBuf>, String> {
+    use std::io::Read;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Failed to read stdin: {e}"))?;
+
+    let separator = match mode {
+        StdinMode::Nul => '\0',
+        StdinMode::Lines => '\n',
+    };
+
+    Ok(input
+        .split(separator)
+        .filter(|p| !p.is_empty())
+        .map(normalize_path)
+        .collect())
+}
+
 fn main() {
     let args = match Cli::parse(std::env::args().skip(1)) {
         Ok(args) => args,
@@ -128,160 +448,330 @@ fn main() {
     };
 
     let root = Path::new(ROOT_DIR);
-    if !root.exists() {
-        fs::create_dir(ROOT_DIR).expect("Failed to create dr default dir");
+    let real_fs = RealFs;
+    if !real_fs.exists(root) {
+        real_fs
+            .create_dir_all(root)
+            .expect("Failed to create dr default dir");
     }
 
     match args.command {
-        Command::Drop => drop_entries(&args.filepaths.unwrap(), root),
-        Command::Delete => delete_entries(&args.filepaths.unwrap(), root),
-        Command::Recover => recover_entries(&args.filepaths.unwrap(), root),
-        Command::List => list_entries(root),
+        Command::Drop => {
+            let matcher = match build_matcher(&real_fs, &args.exclude, args.ignore_file.as_deref())
+            {
+                Ok(m) => m,
+                Err(e) => {
+                    eprintln!("Failed to read ignore file: {e}");
+                    return;
+                }
+            };
+            drop_entries(
+                &real_fs,
+                &args.filepaths.unwrap(),
+                root,
+                &matcher,
+                args.dry_run,
+                args.verbose,
+            )
+        }
+        Command::Delete => {
+            let targets = match resolve_targets(&real_fs, root, args.filepaths, args.edit) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{e}");
+                    return;
+                }
+            };
+            delete_entries(&real_fs, &targets, root, args.dry_run, args.verbose)
+        }
+        Command::Recover => {
+            let targets = match resolve_targets(&real_fs, root, args.filepaths, args.edit) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{e}");
+                    return;
+                }
+            };
+            recover_entries(&real_fs, &targets, root, args.dry_run, args.verbose)
+        }
+        Command::List => list_entries(&real_fs, root),
         Command::Help => println!("{USAGE}"),
     }
 }
 
-fn list_entries(root: &Path) {
-    let entries = match fs::read_dir(root) {
-        Ok(l) => l,
+fn build_matcher(
+    fs: &dyn Fs,
+    exclude: &[String],
+    ignore_file: Option<&Path>,
+) -> io::Result<Matcher> {
+    match ignore_file {
+        Some(path) => Matcher::from_ignore_file(fs, path, exclude),
+        None => Ok(Matcher::new(exclude)),
+    }
+}
+
+/// The manifest entries a recover/delete command should act on.
+///
+/// Explicitly given paths match every manifest entry recorded under that
+/// path, which is the intuitive behaviour for `dr -r foo.txt`. The
+/// interactive editor instead lets the user pick specific manifest lines,
+/// so those are matched by id: two drops of the same path are distinct ids,
+/// and keeping only one line in the editor must act on only that one.
+enum Targets {
+    Paths(Vec<PathBuf>),
+    Ids(Vec<String>),
+}
+
+impl Targets {
+    fn matches(&self, entry: &ManifestEntry) -> bool {
+        match self {
+            Targets::Paths(paths) => paths.contains(&entry.original_abs_path),
+            Targets::Ids(ids) => ids.contains(&entry.id),
+        }
+    }
+}
+
+/// Resolves what a recover/delete command should act on: the explicitly
+/// given paths, unless `--edit` was passed or none were given, in which
+/// case entries are picked interactively from the manifest by id.
+fn resolve_targets(
+    fs: &dyn Fs,
+    root: &Path,
+    filepaths: Option<Vec<PathBuf>>,
+    edit: bool,
+) -> io::Result<Targets> {
+    match filepaths {
+        Some(paths) if !edit => Ok(Targets::Paths(paths)),
+        _ => interactive::select_entries(fs, root).map(Targets::Ids),
+    }
+}
+
+fn list_entries(fs: &dyn Fs, root: &Path) {
+    let entries = match read_manifest(fs, root) {
+        Ok(e) => e,
         Err(e) => {
             eprintln!("{e}");
             return;
         }
     };
 
-    for e in entries {
-        let e = match e {
-            Ok(e) => e,
-            Err(err) => {
-                eprintln!("{err}");
-                continue;
-            }
-        };
-
-        let path = e.path();
-        let filename = path.file_name().unwrap().to_string_lossy();
-
-        if let Some((_, original_name)) = filename.split_once('_') {
-            println!("{original_name}");
-        } else {
-            println!("{filename}");
-        }
+    for entry in entries {
+        println!(
+            "{}\tdropped_at={}\t{}{}",
+            entry.original_abs_path.display(),
+            entry.dropped_at_unix,
+            if entry.is_dir { "dir" } else { "file" },
+            if entry.partial { " (partial, excludes applied)" } else { "" },
+        );
     }
 }
 
-fn recover_entries(filepaths: &[PathBuf], root: &Path) {
-    let dropped_files = match fs::read_dir(root) {
-        Ok(l) => l,
+fn recover_entries(fs: &dyn Fs, targets: &Targets, root: &Path, dry_run: bool, verbose: bool) {
+    let entries = match read_manifest(fs, root) {
+        Ok(e) => e,
         Err(e) => {
             eprintln!("{e}");
             return;
         }
     };
 
-    let dropped_paths: Vec<(PathBuf, PathBuf)> = dropped_files
-        .flatten()
-        .filter_map(|entry| {
-            let stored_path = entry.path();
-            let filename = stored_path.file_name()?.to_string_lossy();
+    let matches: Vec<&ManifestEntry> = entries.iter().filter(|e| targets.matches(e)).collect();
 
-            let (_, original_name) = filename.split_once('_')?;
-            let original_path = PathBuf::from(original_name);
+    let mut recovered_ids = Vec::new();
 
-            if filepaths.contains(&original_path) {
-                Some((stored_path, original_path))
-            } else {
-                None
+    for entry in matches {
+        let stored_path = root.join(&entry.id);
+        let original_path = &entry.original_abs_path;
+
+        if fs.exists(original_path) {
+            if entry.is_dir && entry.partial && fs.is_dir(original_path) {
+                if verbose {
+                    println!(
+                        "merging {} -> {}",
+                        stored_path.display(),
+                        original_path.display()
+                    );
+                }
+
+                if dry_run {
+                    println!("would recover (merge): {}", original_path.display());
+                    continue;
+                }
+
+                if let Err(e) = move_tree_into(fs, &stored_path, original_path) {
+                    eprintln!("Failed to recover {}: {e}", original_path.display());
+                    continue;
+                }
+
+                println!("Recovered: {}", original_path.display());
+                recovered_ids.push(entry.id.clone());
+                continue;
             }
-        })
-        .collect();
 
-    for (stored_path, original_path) in dropped_paths {
-        if original_path.exists() {
             eprintln!("File already exists: {}", original_path.display());
             continue;
         }
 
+        if verbose {
+            println!("{} -> {}", stored_path.display(), original_path.display());
+        }
+
+        if dry_run {
+            println!("would recover: {}", original_path.display());
+            continue;
+        }
+
         if let Some(parent) = original_path.parent() {
-            if let Err(e) = fs::create_dir_all(parent) {
+            if let Err(e) = fs.create_dir_all(parent) {
                 eprintln!("Failed to create directories: {e}");
                 continue;
             }
         }
 
-        if let Err(e) = fs::rename(&stored_path, &original_path) {
+        if let Err(e) = fs.rename(&stored_path, original_path) {
             eprintln!("Failed to recover {}: {e}", original_path.display());
-        } else {
-            println!("Recovered: {}", original_path.display());
+            continue;
+        }
+
+        println!("Recovered: {}", original_path.display());
+        recovered_ids.push(entry.id.clone());
+    }
+
+    if !recovered_ids.is_empty() {
+        if let Err(e) = remove_manifest_entries(fs, root, &recovered_ids) {
+            eprintln!("Failed to update manifest: {e}");
         }
     }
 }
 
-fn delete_entries(filepaths: &[PathBuf], root: &Path) {
-    let dropped_files = match fs::read_dir(root) {
-        Ok(l) => l,
+fn delete_entries(fs: &dyn Fs, targets: &Targets, root: &Path, dry_run: bool, verbose: bool) {
+    let entries = match read_manifest(fs, root) {
+        Ok(e) => e,
         Err(e) => {
-            eprintln!("Error reading dropped files: {e}");
+            eprintln!("Error reading manifest: {e}");
             return;
         }
     };
 
-    let dropped_paths: Vec<PathBuf> = dropped_files
-        .flatten()
-        .filter_map(|entry| {
-            let stored_path = entry.path();
-            let filename = stored_path.file_name()?.to_string_lossy();
+    let matches: Vec<&ManifestEntry> = entries.iter().filter(|e| targets.matches(e)).collect();
 
-            let (_, original_name) = filename.split_once('_')?;
-            let original_path = PathBuf::from(original_name);
+    let mut deleted_ids = Vec::new();
 
-            if filepaths.contains(&original_path) {
-                Some(stored_path)
-            } else {
-                None
-            }
-        })
-        .collect();
+    for entry in matches {
+        let stored_path = root.join(&entry.id);
+
+        if verbose {
+            println!(
+                "{} ({})",
+                stored_path.display(),
+                if entry.is_dir { "dir" } else { "file" },
+            );
+        }
+
+        if dry_run {
+            println!(
+                "would permanently delete: {}",
+                entry.original_abs_path.display()
+            );
+            continue;
+        }
 
-    for path in dropped_paths {
-        let result = if path.is_dir() {
-            fs::remove_dir_all(&path)
+        let result = if entry.is_dir {
+            remove_dir_all_parallel(fs, &stored_path)
         } else {
-            fs::remove_file(&path)
+            fs.remove_file(&stored_path)
         };
 
         if let Err(e) = result {
-            eprintln!("Failed to delete {}: {e}", path.display());
-        } else {
-            println!("Permanently deleted: {}", path.display());
+            eprintln!(
+                "Failed to delete {}: {e}",
+                entry.original_abs_path.display()
+            );
+            continue;
+        }
+
+        println!("Permanently deleted: {}", entry.original_abs_path.display());
+        deleted_ids.push(entry.id.clone());
+    }
+
+    if !deleted_ids.is_empty() {
+        if let Err(e) = remove_manifest_entries(fs, root, &deleted_ids) {
+            eprintln!("Failed to update manifest: {e}");
         }
     }
 }
 
-fn drop_entries(filepaths: &[PathBuf], root: &Path) {
+fn drop_entries(
+    fs: &dyn Fs,
+    filepaths: &[PathBuf],
+    root: &Path,
+    matcher: &Matcher,
+    dry_run: bool,
+    verbose: bool,
+) {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_secs();
 
     for filepath in filepaths {
-        if !filepath.exists() {
+        if !fs.exists(filepath) {
             eprintln!("File not found: {}", filepath.display());
             continue;
         }
 
-        let abs_path = filepath
-            .canonicalize()
+        let abs_path = fs
+            .canonicalize(filepath)
             .unwrap_or_else(|_| filepath.to_path_buf());
-        let stored_name = format!("{now}_{}", abs_path.to_string_lossy());
-        let stored_path = root.join(stored_name);
+        let is_dir = fs.is_dir(filepath);
+        let id = hash_id(fs, root, &abs_path);
+        let stored_path = root.join(&id);
+        let mut partial = false;
+
+        if verbose {
+            println!("{} -> {}", abs_path.display(), stored_path.display());
+        }
+
+        if is_dir && !matcher.is_empty() {
+            let (dropped, skipped) =
+                match walk_filtered(fs, filepath, &stored_path, matcher, dry_run, verbose) {
+                    Ok(counts) => counts,
+                    Err(e) => {
+                        eprintln!("Failed to drop {}: {e}", filepath.display());
+                        continue;
+                    }
+                };
 
-        if let Err(e) = fs::rename(filepath, &stored_path) {
+            let verb = if dry_run { "would drop" } else { "Dropped" };
+            println!(
+                "{verb} {dropped} entr{} from {}, skipped {skipped} matching excludes",
+                if dropped == 1 { "y" } else { "ies" },
+                filepath.display(),
+            );
+
+            if dry_run || dropped == 0 {
+                continue;
+            }
+
+            // walk_filtered moves files out one at a time but never prunes
+            // the (possibly now-empty) source directories, so `filepath`
+            // still exists on disk whether or not anything was actually
+            // skipped. The manifest must say so, or recover/list will claim
+            // the whole original directory is gone when it never moved.
+            partial = fs.exists(filepath);
+        } else if dry_run {
+            println!("would drop {} -> {}", filepath.display(), id);
+            continue;
+        } else if let Err(e) = fs.rename(filepath, &stored_path) {
             if e.kind() == io::ErrorKind::CrossesDevices {
-                let copy_result = if filepath.is_dir() {
-                    copy_dir_all(filepath, &stored_path)
+                if verbose {
+                    println!("cross-device copy fallback used for {}", filepath.display());
+                }
+
+                let copy_result = if is_dir {
+                    fs.copy_dir_all(filepath, &stored_path)
                 } else {
-                    fs::copy(filepath, &stored_path).map(|_| ())
+                    fs.copy_file(filepath, &stored_path).map(|_| ())
                 };
 
                 if let Err(e) = copy_result {
@@ -289,15 +779,15 @@ fn drop_entries(filepaths: &[PathBuf], root: &Path) {
                     continue;
                 }
 
-                let remove_result = if filepath.is_dir() {
-                    fs::remove_dir_all(filepath)
+                let remove_result = if is_dir {
+                    remove_dir_all_parallel(fs, filepath)
                 } else {
-                    fs::remove_file(filepath)
+                    fs.remove_file(filepath)
                 };
 
                 if let Err(e) = remove_result {
                     eprintln!("Failed to remove original {}: {e}", filepath.display());
-                    let _ = fs::remove_file(&stored_path);
+                    let _ = fs.remove_file(&stored_path);
                     continue;
                 }
             } else {
@@ -306,24 +796,424 @@ fn drop_entries(filepaths: &[PathBuf], root: &Path) {
             }
         }
 
+        let entry = ManifestEntry {
+            id,
+            original_abs_path: abs_path,
+            dropped_at_unix: now,
+            is_dir,
+            partial,
+        };
+
+        if let Err(e) = append_manifest_entry(fs, root, &entry) {
+            eprintln!("Failed to record manifest entry: {e}");
+        }
+
         println!("Dropped: {}", filepath.display());
     }
 }
 
-fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
-    fs::create_dir_all(dst)?;
+/// Recursively moves everything under `src` into `dst`, skipping any path
+/// that matches an exclude pattern in `matcher`. A subdirectory whose
+/// entire contents are excluded never gets a corresponding entry created
+/// under `dst`. With `dry_run` set, nothing is actually moved; entries are
+/// only counted (and, if `verbose`, printed). Returns `(dropped, skipped)`
+/// entry counts.
+fn walk_filtered(
+    fs: &dyn Fs,
+    src: &Path,
+    dst: &Path,
+    matcher: &Matcher,
+    dry_run: bool,
+    verbose: bool,
+) -> io::Result<(u64, u64)> {
+    let mut dropped = 0;
+    let mut skipped = 0;
+    walk_filtered_inner(
+        fs, src, src, dst, matcher, dry_run, verbose, &mut dropped, &mut skipped,
+    )?;
+    Ok((dropped, skipped))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk_filtered_inner(
+    fs: &dyn Fs,
+    base: &Path,
+    src: &Path,
+    dst: &Path,
+    matcher: &Matcher,
+    dry_run: bool,
+    verbose: bool,
+    dropped: &mut u64,
+    skipped: &mut u64,
+) -> io::Result<()> {
+    for entry in fs.read_dir(src)? {
+        let relative = entry.strip_prefix(base).unwrap_or(entry.as_path());
+        if matcher.is_excluded(relative) {
+            *skipped += 1;
+            continue;
+        }
+
+        let dst_path = dst.join(entry.file_name().unwrap());
+
+        if fs.is_dir(&entry) {
+            walk_filtered_inner(
+                fs, base, &entry, &dst_path, matcher, dry_run, verbose, dropped, skipped,
+            )?;
+        } else {
+            if verbose {
+                println!("{} -> {}", entry.display(), dst_path.display());
+            }
+
+            if !dry_run {
+                fs.create_dir_all(dst)?;
+                move_file(fs, &entry, &dst_path)?;
+            }
+
+            *dropped += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Recursively moves every entry under `src` into `dst`, creating
+/// directories in `dst` as needed, then removes `src` once it's empty. Used
+/// to recover a partial directory drop back into an original directory
+/// that's still there (the excluded leftovers `walk_filtered` left behind),
+/// as opposed to [`walk_filtered`] which moves *out* of a live source tree
+/// while filtering; this one moves everything, since the stored subtree is
+/// already exactly what was dropped.
+fn move_tree_into(fs: &dyn Fs, src: &Path, dst: &Path) -> io::Result<()> {
+    fs.create_dir_all(dst)?;
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+    for entry in fs.read_dir(src)? {
+        let dst_path = dst.join(entry.file_name().unwrap());
 
-        if src_path.is_dir() {
-            copy_dir_all(&src_path, &dst_path)?;
+        if fs.is_dir(&entry) {
+            move_tree_into(fs, &entry, &dst_path)?;
         } else {
-            fs::copy(&src_path, &dst_path)?;
+            move_file(fs, &entry, &dst_path)?;
         }
     }
 
+    fs.remove_dir_all(src)
+}
+
+fn move_file(fs: &dyn Fs, from: &Path, to: &Path) -> io::Result<()> {
+    if let Err(e) = fs.rename(from, to) {
+        if e.kind() == io::ErrorKind::CrossesDevices {
+            fs.copy_file(from, to)?;
+            fs.remove_file(from)?;
+        } else {
+            return Err(e);
+        }
+    }
     Ok(())
 }
+
+/// Recursively copies `src` into `dst`, recursing into subdirectories and
+/// copying sibling leaf files concurrently via rayon. Per-entry failures
+/// are collected rather than aborting the whole copy, so one unreadable
+/// file doesn't prevent the rest of a large tree from being copied.
+fn copy_dir_all(fs: &dyn Fs, src: &Path, dst: &Path) -> io::Result<()> {
+    fs.create_dir_all(dst)?;
+
+    let entries = fs.read_dir(src)?;
+    let results: Vec<io::Result<()>> = entries
+        .par_iter()
+        .map(|src_path| {
+            let dst_path = dst.join(src_path.file_name().unwrap());
+
+            if fs.is_dir(src_path) {
+                copy_dir_all(fs, src_path, &dst_path)
+            } else {
+                fs.copy_file(src_path, &dst_path).map(|_| ())
+            }
+        })
+        .collect();
+
+    combine_results(results)
+}
+
+/// Recursively removes `path`, recursing into subdirectories and removing
+/// sibling leaf files concurrently via rayon. Mirrors [`copy_dir_all`]'s
+/// shape so the drop fallback and permanent delete get the same
+/// parallel-tree-walk treatment.
+fn remove_dir_all_parallel(fs: &dyn Fs, path: &Path) -> io::Result<()> {
+    let entries = fs.read_dir(path)?;
+    let results: Vec<io::Result<()>> = entries
+        .par_iter()
+        .map(|entry| {
+            if fs.is_dir(entry) {
+                remove_dir_all_parallel(fs, entry)
+            } else {
+                fs.remove_file(entry)
+            }
+        })
+        .collect();
+
+    combine_results(results)?;
+    fs.remove_dir_all(path)
+}
+
+/// Folds per-entry results from a parallel tree walk into a single
+/// `io::Result`, reporting every failure rather than only the first.
+fn combine_results(results: Vec<io::Result<()>>) -> io::Result<()> {
+    let failures: Vec<String> = results
+        .into_iter()
+        .filter_map(|r| r.err())
+        .map(|e| e.to_string())
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(io::Error::other(failures.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::FakeFs;
+
+    fn root() -> PathBuf {
+        PathBuf::from("/tmp/dr")
+    }
+
+    #[test]
+    fn drop_then_list_then_recover() {
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_file("/home/user/foo.txt", b"hello".to_vec());
+
+        drop_entries(&fake, &[PathBuf::from("/home/user/foo.txt")], &root(), &Matcher::new(&[]), false, false);
+        assert!(!fake.exists(Path::new("/home/user/foo.txt")));
+
+        let entries = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original_abs_path, PathBuf::from("/home/user/foo.txt"));
+        assert!(!entries[0].is_dir);
+
+        recover_entries(
+            &fake,
+            &Targets::Paths(vec![PathBuf::from("/home/user/foo.txt")]),
+            &root(),
+            false,
+            false,
+        );
+        assert!(fake.exists(Path::new("/home/user/foo.txt")));
+        assert_eq!(
+            fake.file_contents(Path::new("/home/user/foo.txt")),
+            Some(b"hello".to_vec())
+        );
+        assert!(read_manifest(&fake, &root()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn drop_then_delete_removes_manifest_entry() {
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_file("/home/user/bar.txt", b"bye".to_vec());
+
+        drop_entries(&fake, &[PathBuf::from("/home/user/bar.txt")], &root(), &Matcher::new(&[]), false, false);
+        delete_entries(
+            &fake,
+            &Targets::Paths(vec![PathBuf::from("/home/user/bar.txt")]),
+            &root(),
+            false,
+            false,
+        );
+
+        assert!(read_manifest(&fake, &root()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn drop_directory_copies_recursively_on_cross_device_fallback() {
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_dir("/home/user/mydir");
+        fake.insert_file("/home/user/mydir/a.txt", b"a".to_vec());
+        fake.insert_file("/home/user/mydir/nested/b.txt", b"b".to_vec());
+
+        drop_entries(&fake, &[PathBuf::from("/home/user/mydir")], &root(), &Matcher::new(&[]), false, false);
+
+        let entries = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].is_dir);
+
+        let stored = root().join(&entries[0].id);
+        assert_eq!(
+            fake.file_contents(&stored.join("a.txt")),
+            Some(b"a".to_vec())
+        );
+        assert_eq!(
+            fake.file_contents(&stored.join("nested/b.txt")),
+            Some(b"b".to_vec())
+        );
+    }
+
+    #[test]
+    fn drop_directory_honours_exclude_patterns() {
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_dir("/home/user/mydir");
+        fake.insert_file("/home/user/mydir/keep.txt", b"keep".to_vec());
+        fake.insert_file("/home/user/mydir/debug.log", b"noisy".to_vec());
+
+        let matcher = Matcher::new(&["*.log".to_string()]);
+        drop_entries(
+            &fake,
+            &[PathBuf::from("/home/user/mydir")],
+            &root(),
+            &matcher,
+            false,
+            false,
+        );
+
+        let entries = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let stored = root().join(&entries[0].id);
+        assert_eq!(
+            fake.file_contents(&stored.join("keep.txt")),
+            Some(b"keep".to_vec())
+        );
+        assert!(fake.file_contents(&stored.join("debug.log")).is_none());
+        assert_eq!(
+            fake.file_contents(Path::new("/home/user/mydir/debug.log")),
+            Some(b"noisy".to_vec())
+        );
+    }
+
+    #[test]
+    fn recovering_a_partial_directory_drop_merges_excluded_leftovers() {
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_dir("/home/user/mydir");
+        fake.insert_file("/home/user/mydir/keep.txt", b"keep".to_vec());
+        fake.insert_file("/home/user/mydir/debug.log", b"noisy".to_vec());
+
+        let matcher = Matcher::new(&["*.log".to_string()]);
+        drop_entries(
+            &fake,
+            &[PathBuf::from("/home/user/mydir")],
+            &root(),
+            &matcher,
+            false,
+            false,
+        );
+
+        // The original directory still exists (debug.log was left behind),
+        // so the manifest must record this as a partial drop rather than
+        // claiming the whole directory was dropped.
+        assert!(fake.exists(Path::new("/home/user/mydir")));
+        let entries = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].is_dir);
+        assert!(entries[0].partial);
+
+        recover_entries(
+            &fake,
+            &Targets::Paths(vec![PathBuf::from("/home/user/mydir")]),
+            &root(),
+            false,
+            false,
+        );
+
+        assert_eq!(
+            fake.file_contents(Path::new("/home/user/mydir/keep.txt")),
+            Some(b"keep".to_vec())
+        );
+        assert_eq!(
+            fake.file_contents(Path::new("/home/user/mydir/debug.log")),
+            Some(b"noisy".to_vec())
+        );
+        assert!(read_manifest(&fake, &root()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn drop_directory_with_no_matching_excludes_is_still_recoverable() {
+        // walk_filtered never prunes the source directories it empties, so
+        // `mydir` still exists on disk after the drop even though every
+        // entry in it was moved out (skipped == 0). The manifest must still
+        // mark this as partial so recover doesn't refuse with "already
+        // exists" and strand the dropped files.
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_dir("/home/user/mydir");
+        fake.insert_file("/home/user/mydir/a.txt", b"a".to_vec());
+
+        let matcher = Matcher::new(&["*.log".to_string()]);
+        drop_entries(
+            &fake,
+            &[PathBuf::from("/home/user/mydir")],
+            &root(),
+            &matcher,
+            false,
+            false,
+        );
+
+        assert!(fake.exists(Path::new("/home/user/mydir")));
+        let entries = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].partial);
+
+        recover_entries(
+            &fake,
+            &Targets::Paths(vec![PathBuf::from("/home/user/mydir")]),
+            &root(),
+            false,
+            false,
+        );
+
+        assert_eq!(
+            fake.file_contents(Path::new("/home/user/mydir/a.txt")),
+            Some(b"a".to_vec())
+        );
+        assert!(read_manifest(&fake, &root()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn recovering_by_id_only_acts_on_the_selected_drop() {
+        // Two drops of the same path share an original_abs_path but have
+        // distinct ids; picking one line in the editor (Targets::Ids) must
+        // act on only that manifest entry, not every entry for that path.
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_file("/home/user/dup.txt", b"first".to_vec());
+        drop_entries(&fake, &[PathBuf::from("/home/user/dup.txt")], &root(), &Matcher::new(&[]), false, false);
+        fake.insert_file("/home/user/dup.txt", b"second".to_vec());
+        drop_entries(&fake, &[PathBuf::from("/home/user/dup.txt")], &root(), &Matcher::new(&[]), false, false);
+
+        let entries = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(entries.len(), 2);
+        let first_id = entries[0].id.clone();
+
+        recover_entries(&fake, &Targets::Ids(vec![first_id.clone()]), &root(), false, false);
+
+        assert!(fake.exists(Path::new("/home/user/dup.txt")));
+        let remaining = read_manifest(&fake, &root()).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_ne!(remaining[0].id, first_id);
+    }
+
+    #[test]
+    fn dry_run_leaves_filesystem_and_manifest_untouched() {
+        let fake = FakeFs::new();
+        fake.insert_dir(root());
+        fake.insert_file("/home/user/dry.txt", b"keep".to_vec());
+
+        drop_entries(
+            &fake,
+            &[PathBuf::from("/home/user/dry.txt")],
+            &root(),
+            &Matcher::new(&[]),
+            true,
+            false,
+        );
+
+        assert!(fake.exists(Path::new("/home/user/dry.txt")));
+        assert!(read_manifest(&fake, &root()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! A small glob/gitignore-style matcher used to exclude paths when
+//! dropping a directory tree, modelled on the matcher layer used by
+//! Mercurial to decide which working-directory paths a command applies
+//! to.
+
+use crate::fs::Fs;
+use std::path::Path;
+
+/// A compiled set of exclude patterns. Each pattern is split into `/`
+/// separated segments; a pattern with no `/` is implicitly anchored with
+/// a leading `**` so it matches its basename at any depth, mirroring how
+/// a bare `.gitignore` entry behaves.
+pub struct Matcher {
+    patterns: Vec<Vec<String>>,
+}
+
+impl Matcher {
+    pub fn new(patterns: &[String]) -> Matcher {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                let mut segs: Vec<String> = p.split('/').filter(|s| !s.is_empty()).map(String::from).collect();
+                if !p.contains('/') {
+                    segs = vec!["**".to_string(), p.to_string()];
+                }
+                segs
+            })
+            .collect();
+
+        Matcher { patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Parses an `--ignore-file`: blank lines and `#` comments are
+    /// skipped, every other line becomes a pattern.
+    pub fn from_ignore_file(fs: &dyn Fs, path: &Path, extra: &[String]) -> std::io::Result<Matcher> {
+        let contents = fs.read_to_string(path)?;
+        let mut patterns: Vec<String> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect();
+        patterns.extend(extra.iter().cloned());
+        Ok(Matcher::new(&patterns))
+    }
+
+    /// Returns true if `relative_path` (relative to the directory being
+    /// dropped) matches any exclude pattern.
+    pub fn is_excluded(&self, relative_path: &Path) -> bool {
+        let path_segs: Vec<&str> = relative_path
+            .to_str()
+            .into_iter()
+            .flat_map(|s| s.split('/'))
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        self.patterns
+            .iter()
+            .any(|pat| match_segments(pat, &path_segs))
+    }
+}
+
+fn match_segments(pat: &[String], path: &[&str]) -> bool {
+    match pat.first() {
+        None => path.is_empty(),
+        Some(seg) if seg == "**" => {
+            if match_segments(&pat[1..], path) {
+                return true;
+            }
+            !path.is_empty() && match_segments(pat, &path[1..])
+        }
+        Some(seg) => {
+            if path.is_empty() {
+                return false;
+            }
+            segment_match(seg, path[0]) && match_segments(&pat[1..], &path[1..])
+        }
+    }
+}
+
+/// Matches a single path segment against a pattern containing `*` (any
+/// run of characters) and `?` (any single character).
+fn segment_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    segment_match_rec(&p, &t)
+}
+
+fn segment_match_rec(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len()).any(|i| segment_match_rec(&pattern[1..], &text[i..])),
+        Some('?') => !text.is_empty() && segment_match_rec(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && segment_match_rec(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_pattern_matches_at_any_depth() {
+        let m = Matcher::new(&["*.log".to_string()]);
+        assert!(m.is_excluded(Path::new("a.log")));
+        assert!(m.is_excluded(Path::new("nested/deep/a.log")));
+        assert!(!m.is_excluded(Path::new("a.txt")));
+    }
+
+    #[test]
+    fn anchored_double_star_matches_subtree_and_dir_itself() {
+        let m = Matcher::new(&["target/**".to_string()]);
+        assert!(m.is_excluded(Path::new("target")));
+        assert!(m.is_excluded(Path::new("target/debug/build")));
+        assert!(!m.is_excluded(Path::new("src/target_thing")));
+    }
+}